@@ -1,16 +1,19 @@
 use futures_util::future::BoxFuture;
 use hyper::{header::AUTHORIZATION, http::HeaderValue, Request};
 use parking_lot::RwLock;
+use rand::Rng;
 use std::{
     fmt,
     sync::Arc,
     task::{ready, Context, Poll},
+    time::{Duration, SystemTime},
 };
-use tracing::trace;
+use tracing::{trace, warn};
 
 use crate::{
-    client::{self, KeycloakClient},
+    client::{Grant, KeycloakClient, KeycloakClientConfig},
     error::Result,
+    store::TokenStore,
     sync::RefGuard,
     token::Token,
 };
@@ -23,21 +26,20 @@ pub struct KeycloakAuth {
 }
 
 impl KeycloakAuth {
-    pub fn new(
-        server_url: String,
-        realm: String,
-        client_id: String,
-        client_secret: String,
-    ) -> Result<Self> {
-        Ok(Self {
-            inner: Arc::new(RwLock::new(KeycloakAuthInner::new(
-                client::KeycloakClient::new(
-                    format!("{server_url}/realms/{realm}/protocol/openid-connect/token"),
-                    client_id,
-                    client_secret,
-                )?,
-            ))),
-        })
+    /// Enables proactive background refresh: once a token has been fetched, a
+    /// background task refetches it shortly before expiry and atomically
+    /// swaps it in, so `poll_ready` almost never has to wait on the network.
+    pub fn with_background_refresh(self) -> Self {
+        self.inner.write().background_refresh = true;
+        self
+    }
+
+    /// Loads a still-valid token from `store` before hitting the network on
+    /// first `poll_ready`, and persists the token there after each successful
+    /// fetch.
+    pub fn with_token_store(self, store: impl TokenStore + 'static) -> Self {
+        self.inner.write().store = Some(Arc::new(store));
+        self
     }
 
     pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
@@ -45,7 +47,23 @@ impl KeycloakAuth {
             return Poll::Ready(Ok(()));
         }
 
-        self.inner.write().poll_ready(cx)
+        let result = self.inner.write().poll_ready(cx);
+
+        if let Poll::Ready(Ok(())) = result {
+            self.maybe_spawn_background_refresh();
+        }
+
+        result
+    }
+
+    fn maybe_spawn_background_refresh(&self) {
+        let mut inner = self.inner.write();
+
+        if inner.background_refresh && !inner.refresh_task_spawned {
+            inner.refresh_task_spawned = true;
+            drop(inner);
+            spawn_background_refresh(self.inner.clone());
+        }
     }
 
     pub fn update_request<T>(&mut self, req: &mut Request<T>) {
@@ -54,16 +72,118 @@ impl KeycloakAuth {
     }
 }
 
+/// Builds a [`KeycloakAuth`], replacing the previous positional constructor
+/// with knobs for the request timeout, retry policy, TLS/`reqwest::Client`
+/// customization, an optional token `scope`, and the refresh margin applied
+/// to fetched tokens.
+pub struct KeycloakAuthBuilder {
+    server_url: String,
+    realm: String,
+    client_id: String,
+    client_secret: String,
+    client_config: KeycloakClientConfig,
+    grant: Grant,
+}
+
+impl KeycloakAuthBuilder {
+    pub fn new(server_url: String, realm: String, client_id: String, client_secret: String) -> Self {
+        Self {
+            server_url,
+            realm,
+            client_id,
+            client_secret,
+            client_config: KeycloakClientConfig::default(),
+            grant: Grant::ClientCredentials,
+        }
+    }
+
+    /// The grant used for the initial token fetch and for any subsequent
+    /// refetch that can't use a refresh token (e.g. because the realm didn't
+    /// return one, or it expired). Defaults to [`Grant::ClientCredentials`];
+    /// set this to `Grant::Password { .. }` or `Grant::AuthorizationCode { .. }`
+    /// to authenticate on behalf of a user instead of a service account.
+    pub fn grant(mut self, grant: Grant) -> Self {
+        self.grant = grant;
+        self
+    }
+
+    /// Request timeout applied to the underlying `reqwest` client.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.client_config.timeout = timeout;
+        self
+    }
+
+    /// Maximum number of retries, applied both to the transport-level retries
+    /// of `RetryTransientMiddleware` and to the retries of transient OAuth
+    /// errors in `KeycloakClient::fetch_token`.
+    pub fn max_retries(mut self, max_retries: u32) -> Self {
+        self.client_config.max_retries = max_retries;
+        self
+    }
+
+    /// Lower and upper bounds of the exponential backoff between retries, for
+    /// both retry layers described on [`max_retries`](Self::max_retries).
+    pub fn retry_interval(mut self, min: Duration, max: Duration) -> Self {
+        self.client_config.min_retry_interval = min;
+        self.client_config.max_retry_interval = max;
+        self
+    }
+
+    /// `scope` appended to the token request body.
+    pub fn scope(mut self, scope: impl Into<String>) -> Self {
+        self.client_config.scope = Some(scope.into());
+        self
+    }
+
+    /// Margin before a token's actual expiry at which it is considered
+    /// expired, triggering a refetch.
+    pub fn expiry_delta(mut self, expiry_delta: Duration) -> Self {
+        self.client_config.expiry_delta = expiry_delta;
+        self
+    }
+
+    /// Custom `reqwest::ClientBuilder` backing the token-endpoint client,
+    /// e.g. to configure a private CA or mTLS against a self-hosted realm.
+    pub fn client_builder(mut self, client_builder: reqwest::ClientBuilder) -> Self {
+        self.client_config.client_builder = Some(client_builder);
+        self
+    }
+
+    pub fn build(self) -> Result<KeycloakAuth> {
+        let client = KeycloakClient::new(
+            format!(
+                "{}/realms/{}/protocol/openid-connect/token",
+                self.server_url, self.realm
+            ),
+            self.client_id,
+            self.client_secret,
+            self.client_config,
+        )?;
+
+        Ok(KeycloakAuth {
+            inner: Arc::new(RwLock::new(KeycloakAuthInner::new(client, self.grant))),
+        })
+    }
+}
+
 struct KeycloakAuthInner {
     state: State,
     client: KeycloakClient,
+    background_refresh: bool,
+    refresh_task_spawned: bool,
+    store: Option<Arc<dyn TokenStore>>,
+    grant: Grant,
 }
 
 impl KeycloakAuthInner {
-    pub fn new(client: KeycloakClient) -> Self {
+    pub fn new(client: KeycloakClient, grant: Grant) -> Self {
         Self {
             state: State::NotFetched,
             client,
+            background_refresh: false,
+            refresh_task_spawned: false,
+            store: None,
+            grant,
         }
     }
 
@@ -88,15 +208,32 @@ impl KeycloakAuthInner {
                 State::NotFetched => {
                     trace!("State::NotFetched");
 
+                    let stored_token = self
+                        .store
+                        .as_ref()
+                        .and_then(|store| store.load())
+                        .filter(|token| !token.is_expired());
+
+                    if let Some(token) = stored_token {
+                        trace!("loaded valid token from store");
+                        self.state = State::Fetched { token };
+                        return Poll::Ready(Ok(()));
+                    }
+
                     self.state = {
                         State::Fetching {
-                            fut: RefGuard::new(self.client.fetch_token_boxed()),
+                            fut: RefGuard::new(
+                                self.client.fetch_token_boxed(self.grant.clone()),
+                            ),
                         }
                     };
                 }
                 State::Fetching { ref mut fut } => match ready!(fut.get_mut().as_mut().poll(cx)) {
                     Ok(token) => {
                         trace!("State::Fetching {:?}", token);
+                        if let Some(store) = &self.store {
+                            store.store(&token);
+                        }
                         self.state = State::Fetched { token };
                         return Poll::Ready(Ok(()));
                     }
@@ -109,6 +246,9 @@ impl KeycloakAuthInner {
                     match ready!(fut.get_mut().as_mut().poll(cx)) {
                         Ok(token) => {
                             trace!("State::Refetching {:?}", token);
+                            if let Some(store) = &self.store {
+                                store.store(&token);
+                            }
                             self.state = State::Fetched { token };
                             return Poll::Ready(Ok(()));
                         }
@@ -121,9 +261,20 @@ impl KeycloakAuthInner {
                 State::Fetched { ref token } => {
                     trace!("State::Fetched (token is expired)");
 
+                    let grant = if token.has_valid_refresh_token() {
+                        Grant::RefreshToken {
+                            token: token
+                                .refresh_token
+                                .clone()
+                                .expect("has_valid_refresh_token implies refresh_token is set"),
+                        }
+                    } else {
+                        self.grant.clone()
+                    };
+
                     self.state = {
                         State::Refetching {
-                            fut: RefGuard::new(self.client.fetch_token_boxed()),
+                            fut: RefGuard::new(self.client.fetch_token_boxed(grant)),
                             token: token.clone(),
                         }
                     };
@@ -133,6 +284,111 @@ impl KeycloakAuthInner {
     }
 }
 
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+const MAX_JITTER: Duration = Duration::from_secs(5);
+
+/// Refetches `inner`'s token shortly before it expires and swaps it in,
+/// keeping `can_skip_poll_ready` true for as long as possible. Retries
+/// retryable OAuth errors with backoff only while the held token is not yet
+/// expired in the [`Token::is_expired`] sense, i.e. still outside its
+/// `expiry_delta` margin; since the refresh deadline already lies at that
+/// margin minus up to `MAX_JITTER`, this leaves at most the jitter window for
+/// retries. Once the token is within the margin, a failed attempt resets the
+/// state to `NotFetched` and the lazy `poll_ready` path fetches a new token;
+/// the old token is not kept in use until its hard expiry. A non-retryable
+/// error stops the loop and leaves the current token in place.
+fn spawn_background_refresh(inner: Arc<RwLock<KeycloakAuthInner>>) {
+    tokio::spawn(async move {
+        loop {
+            let (client, store, token, grant) = {
+                let guard = inner.read();
+                match guard.state {
+                    State::Fetched { ref token } => (
+                        guard.client.clone(),
+                        guard.store.clone(),
+                        token.clone(),
+                        guard.grant.clone(),
+                    ),
+                    _ => {
+                        // A concurrent lazy `poll_ready` moved the token out of
+                        // `Fetched` (e.g. into `Refetching`) before we got to
+                        // it. Stop this cycle, but let a future successful
+                        // fetch respawn background refresh instead of leaving
+                        // it silently disabled for the rest of this auth's
+                        // lifetime.
+                        drop(guard);
+                        inner.write().refresh_task_spawned = false;
+                        return;
+                    }
+                }
+            };
+
+            let jitter = Duration::from_millis(rand::thread_rng().gen_range(0..MAX_JITTER.as_millis() as u64));
+            let deadline = token
+                .expiration
+                .checked_sub(token.expiry_delta)
+                .unwrap_or_else(SystemTime::now)
+                .checked_sub(jitter)
+                .unwrap_or_else(SystemTime::now);
+
+            let sleep_duration = deadline.duration_since(SystemTime::now()).unwrap_or_default();
+
+            tokio::time::sleep(sleep_duration).await;
+
+            let grant = if token.has_valid_refresh_token() {
+                Grant::RefreshToken {
+                    token: token
+                        .refresh_token
+                        .clone()
+                        .expect("has_valid_refresh_token implies refresh_token is set"),
+                }
+            } else {
+                grant
+            };
+
+            let mut backoff = Duration::from_secs(1);
+
+            loop {
+                match client.fetch_token(&grant).await {
+                    Ok(new_token) => {
+                        trace!("background refresh succeeded");
+                        if let Some(store) = &store {
+                            store.store(&new_token);
+                        }
+                        inner.write().state = State::Fetched { token: new_token };
+                        break;
+                    }
+                    Err(err) if token.is_expired() => {
+                        warn!(?err, "background refresh failed after token expiry, falling back to lazy fetch");
+                        let mut guard = inner.write();
+                        guard.state = State::NotFetched;
+                        guard.refresh_task_spawned = false;
+                        return;
+                    }
+                    Err(err) => {
+                        let retryable =
+                            matches!(&err, crate::Error::OAuth(oauth_err) if oauth_err.is_retryable());
+
+                        if !retryable {
+                            // The held token is still valid, so keep serving it
+                            // (leave `state` as `Fetched`) instead of forcing a
+                            // lazy refetch on the next request; just stop this
+                            // retry loop and let a later poll_ready respawn it.
+                            warn!(?err, "background refresh failed with a non-retryable error, keeping current token");
+                            inner.write().refresh_task_spawned = false;
+                            return;
+                        }
+
+                        warn!(?err, ?backoff, "background refresh failed, retrying");
+                        tokio::time::sleep(backoff).await;
+                        backoff = (backoff * 2).min(MAX_BACKOFF);
+                    }
+                }
+            }
+        }
+    });
+}
+
 pub(crate) enum State {
     NotFetched,
     Fetching {
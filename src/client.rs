@@ -5,9 +5,11 @@ use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use reqwest_tracing::{SpanBackendWithUrl, TracingMiddleware};
 use serde::Deserialize;
 use std::time::Duration;
-use tracing::instrument;
+use tracing::{debug, instrument};
 
-use crate::{auth::TokenResponseFuture, error::Result, token::Token, Error};
+use crate::{
+    auth::TokenResponseFuture, error::Result, token::Token, validation::JwksResponseFuture, Error,
+};
 
 #[derive(Clone)]
 pub struct KeycloakClient {
@@ -15,10 +17,48 @@ pub struct KeycloakClient {
     pub token_url: Url,
     pub client_id: String,
     pub client_secret: String,
+    pub scope: Option<String>,
+    pub expiry_delta: Duration,
+    pub max_retries: u32,
+    pub min_retry_interval: Duration,
+    pub max_retry_interval: Duration,
+}
+
+/// Tunables for [`KeycloakClient`], previously hardcoded: request timeout,
+/// retry bounds, the custom `reqwest::ClientBuilder` backing the inner
+/// client (for private CAs / mTLS), an optional `scope` to request, and the
+/// refresh margin applied to fetched tokens.
+pub struct KeycloakClientConfig {
+    pub timeout: Duration,
+    pub max_retries: u32,
+    pub min_retry_interval: Duration,
+    pub max_retry_interval: Duration,
+    pub scope: Option<String>,
+    pub expiry_delta: Duration,
+    pub client_builder: Option<reqwest::ClientBuilder>,
+}
+
+impl Default for KeycloakClientConfig {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(2),
+            max_retries: 3,
+            min_retry_interval: Duration::from_millis(100),
+            max_retry_interval: Duration::from_secs(30),
+            scope: None,
+            expiry_delta: Token::EXPIRY_DELTA,
+            client_builder: None,
+        }
+    }
 }
 
 impl KeycloakClient {
-    pub fn new(token_url: String, client_id: String, client_secret: String) -> Result<Self> {
+    pub fn new(
+        token_url: String,
+        client_id: String,
+        client_secret: String,
+        config: KeycloakClientConfig,
+    ) -> Result<Self> {
         let mut default_headers = HeaderMap::new();
 
         default_headers.insert(
@@ -26,12 +66,16 @@ impl KeycloakClient {
             HeaderValue::from_static("application/x-www-form-urlencoded"),
         );
 
-        let inner_client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(2))
+        let inner_client = config
+            .client_builder
+            .unwrap_or_else(reqwest::Client::builder)
+            .timeout(config.timeout)
             .default_headers(default_headers)
             .build()?;
 
-        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+        let retry_policy = ExponentialBackoff::builder()
+            .retry_bounds(config.min_retry_interval, config.max_retry_interval)
+            .build_with_max_retries(config.max_retries);
 
         let client = ClientBuilder::new(inner_client)
             .with(TracingMiddleware::<SpanBackendWithUrl>::new())
@@ -43,28 +87,86 @@ impl KeycloakClient {
             token_url: Url::parse(&token_url)?,
             client_id,
             client_secret,
+            scope: config.scope,
+            expiry_delta: config.expiry_delta,
+            max_retries: config.max_retries,
+            min_retry_interval: config.min_retry_interval,
+            max_retry_interval: config.max_retry_interval,
         })
     }
 
+    /// Fetches a token, retrying requests that the token endpoint itself
+    /// reports as transient (e.g. `temporarily_unavailable`, `slow_down`)
+    /// with backoff, distinct from [`RetryTransientMiddleware`]'s
+    /// transport-level retries, and failing fast on OAuth errors that won't
+    /// resolve by retrying (e.g. `invalid_client`, `invalid_grant`).
     #[instrument(skip(self))]
-    pub async fn fetch_token(&self) -> Result<Token> {
+    pub async fn fetch_token(&self, grant: &Grant) -> Result<Token> {
+        let mut backoff = self.min_retry_interval;
+
+        for attempt in 0.. {
+            match self.fetch_token_once(grant).await {
+                Ok(token) => return Ok(token),
+                Err(err) => {
+                    let retryable = matches!(&err, Error::OAuth(oauth_err) if oauth_err.is_retryable());
+
+                    if !retryable || attempt >= self.max_retries {
+                        return Err(err);
+                    }
+
+                    debug!(?err, attempt, "retrying token request after transient OAuth error");
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(self.max_retry_interval);
+                }
+            }
+        }
+
+        unreachable!("for 0.. never exhausts")
+    }
+
+    async fn fetch_token_once(&self, grant: &Grant) -> Result<Token> {
+        let mut params: Vec<(&str, &str)> =
+            vec![("grant_type", grant.as_str()), ("client_id", &self.client_id)];
+
+        match grant {
+            Grant::ClientCredentials => {}
+            Grant::Password { username, password } => {
+                params.push(("username", username));
+                params.push(("password", password));
+            }
+            Grant::RefreshToken { token } => {
+                params.push(("refresh_token", token));
+            }
+            Grant::AuthorizationCode { code, redirect_uri } => {
+                params.push(("code", code));
+                params.push(("redirect_uri", redirect_uri));
+            }
+        }
+
+        if let Some(scope) = &self.scope {
+            params.push(("scope", scope));
+        }
+
         let response = self
             .inner
             .post(self.token_url.clone())
             .basic_auth(&self.client_id, Some(&self.client_secret))
-            .body("grant_type=client_credentials")
+            .form(&params)
             .send()
             .await?;
 
         let status = response.status();
 
         if !status.is_success() {
-            let status_code = response.status().as_u16();
+            let status_code = status.as_u16();
             let response_text = response.text().await?;
 
-            return Err(Error::FetchToken {
-                status_code,
-                response_text,
+            return Err(match serde_json::from_str::<OAuthErrorBody>(&response_text) {
+                Ok(body) => Error::OAuth(OAuthError::from_body(body)),
+                Err(_) => Error::FetchToken {
+                    status_code,
+                    response_text,
+                },
             });
         }
 
@@ -74,14 +176,83 @@ impl KeycloakClient {
             &token_response.token_type,
             &token_response.access_token,
             token_response.expires_in,
+            token_response.refresh_token,
+            token_response.refresh_expires_in,
+            self.expiry_delta,
         );
 
         Ok(token)
     }
 
-    pub fn fetch_token_boxed(&self) -> TokenResponseFuture {
+    pub fn fetch_token_boxed(&self, grant: Grant) -> TokenResponseFuture {
         let client = self.clone();
-        Box::pin(async move { client.fetch_token().await })
+        Box::pin(async move { client.fetch_token(&grant).await })
+    }
+}
+
+/// RFC 6749 §5.2 error response body returned by the token endpoint.
+#[derive(Debug, Clone, Deserialize)]
+pub struct OAuthErrorBody {
+    pub error: String,
+    #[serde(default)]
+    pub error_description: Option<String>,
+}
+
+/// A typed RFC 6749 token-endpoint error, classified so callers can match on
+/// the specific failure instead of string-scraping the response body.
+#[derive(Debug, Clone, thiserror::Error)]
+pub enum OAuthError {
+    #[error("invalid_client: {0:?}")]
+    InvalidClient(Option<String>),
+    #[error("invalid_grant: {0:?}")]
+    InvalidGrant(Option<String>),
+    #[error("invalid_scope: {0:?}")]
+    InvalidScope(Option<String>),
+    #[error("unauthorized_client: {0:?}")]
+    UnauthorizedClient(Option<String>),
+    #[error("unsupported_grant_type: {0:?}")]
+    UnsupportedGrantType(Option<String>),
+    #[error("temporarily_unavailable: {0:?}")]
+    TemporarilyUnavailable(Option<String>),
+    #[error("slow_down: {0:?}")]
+    SlowDown(Option<String>),
+    #[error("{error}: {error_description:?}")]
+    Other {
+        error: String,
+        error_description: Option<String>,
+    },
+}
+
+impl OAuthError {
+    fn from_body(body: OAuthErrorBody) -> Self {
+        let OAuthErrorBody {
+            error,
+            error_description,
+        } = body;
+
+        match error.as_str() {
+            "invalid_client" => OAuthError::InvalidClient(error_description),
+            "invalid_grant" => OAuthError::InvalidGrant(error_description),
+            "invalid_scope" => OAuthError::InvalidScope(error_description),
+            "unauthorized_client" => OAuthError::UnauthorizedClient(error_description),
+            "unsupported_grant_type" => OAuthError::UnsupportedGrantType(error_description),
+            "temporarily_unavailable" => OAuthError::TemporarilyUnavailable(error_description),
+            "slow_down" => OAuthError::SlowDown(error_description),
+            _ => OAuthError::Other {
+                error,
+                error_description,
+            },
+        }
+    }
+
+    /// Whether retrying the token request is likely to succeed, as opposed
+    /// to failing fast on a client misconfiguration that won't resolve on
+    /// its own.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            OAuthError::TemporarilyUnavailable(_) | OAuthError::SlowDown(_)
+        )
     }
 }
 
@@ -90,4 +261,166 @@ pub struct TokenResponse {
     pub token_type: String,
     pub access_token: String,
     pub expires_in: u64,
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    #[serde(default)]
+    pub refresh_expires_in: Option<u64>,
+}
+
+/// The OAuth2 grant used to obtain or renew a [`Token`] from the realm's
+/// token endpoint.
+#[derive(Debug, Clone)]
+pub enum Grant {
+    ClientCredentials,
+    Password { username: String, password: String },
+    RefreshToken { token: String },
+    AuthorizationCode { code: String, redirect_uri: String },
+}
+
+impl Grant {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Grant::ClientCredentials => "client_credentials",
+            Grant::Password { .. } => "password",
+            Grant::RefreshToken { .. } => "refresh_token",
+            Grant::AuthorizationCode { .. } => "authorization_code",
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct KeycloakJwksClient {
+    pub inner: ClientWithMiddleware,
+    pub certs_url: Url,
+}
+
+impl KeycloakJwksClient {
+    pub fn new(certs_url: String) -> Result<Self> {
+        let inner_client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(2))
+            .build()?;
+
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
+
+        let client = ClientBuilder::new(inner_client)
+            .with(TracingMiddleware::<SpanBackendWithUrl>::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        Ok(Self {
+            inner: client,
+            certs_url: Url::parse(&certs_url)?,
+        })
+    }
+
+    #[instrument(skip(self))]
+    pub async fn fetch_jwks(&self) -> Result<Jwks> {
+        let response = self.inner.get(self.certs_url.clone()).send().await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let status_code = status.as_u16();
+            let response_text = response.text().await?;
+
+            return Err(Error::Jwks {
+                status_code,
+                response_text,
+            });
+        }
+
+        let jwks = response.json::<Jwks>().await?;
+
+        Ok(jwks)
+    }
+
+    pub fn fetch_jwks_boxed(&self) -> JwksResponseFuture {
+        let client = self.clone();
+        Box::pin(async move { client.fetch_jwks().await })
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Jwk {
+    pub kid: String,
+    pub kty: String,
+    pub alg: Option<String>,
+    pub n: Option<String>,
+    pub e: Option<String>,
+    pub crv: Option<String>,
+    pub x: Option<String>,
+    pub y: Option<String>,
+    pub x5c: Option<Vec<String>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn grant_as_str_matches_rfc6749_grant_type() {
+        assert_eq!(Grant::ClientCredentials.as_str(), "client_credentials");
+        assert_eq!(
+            Grant::Password {
+                username: "user".into(),
+                password: "pass".into(),
+            }
+            .as_str(),
+            "password"
+        );
+        assert_eq!(
+            Grant::RefreshToken { token: "refresh".into() }.as_str(),
+            "refresh_token"
+        );
+        assert_eq!(
+            Grant::AuthorizationCode {
+                code: "code".into(),
+                redirect_uri: "https://example.com/callback".into(),
+            }
+            .as_str(),
+            "authorization_code"
+        );
+    }
+
+    fn oauth_error(error: &str) -> OAuthError {
+        OAuthError::from_body(OAuthErrorBody {
+            error: error.to_string(),
+            error_description: None,
+        })
+    }
+
+    #[test]
+    fn from_body_classifies_known_errors() {
+        assert!(matches!(oauth_error("invalid_client"), OAuthError::InvalidClient(_)));
+        assert!(matches!(oauth_error("invalid_grant"), OAuthError::InvalidGrant(_)));
+        assert!(matches!(oauth_error("invalid_scope"), OAuthError::InvalidScope(_)));
+        assert!(matches!(
+            oauth_error("unauthorized_client"),
+            OAuthError::UnauthorizedClient(_)
+        ));
+        assert!(matches!(
+            oauth_error("unsupported_grant_type"),
+            OAuthError::UnsupportedGrantType(_)
+        ));
+        assert!(matches!(
+            oauth_error("temporarily_unavailable"),
+            OAuthError::TemporarilyUnavailable(_)
+        ));
+        assert!(matches!(oauth_error("slow_down"), OAuthError::SlowDown(_)));
+        assert!(matches!(oauth_error("something_else"), OAuthError::Other { .. }));
+    }
+
+    #[test]
+    fn is_retryable_only_for_transient_errors() {
+        assert!(oauth_error("temporarily_unavailable").is_retryable());
+        assert!(oauth_error("slow_down").is_retryable());
+        assert!(!oauth_error("invalid_client").is_retryable());
+        assert!(!oauth_error("invalid_grant").is_retryable());
+        assert!(!oauth_error("unsupported_grant_type").is_retryable());
+    }
 }
@@ -1,3 +1,5 @@
+use crate::client::OAuthError;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("Invalid URL: {0}")]
@@ -7,10 +9,21 @@ pub enum Error {
         status_code: u16,
         response_text: String,
     },
+    #[error("oauth error fetching token: {0}")]
+    OAuth(#[from] OAuthError),
     #[error("http request error: {0}")]
     HttpRequest(#[from] reqwest::Error),
     #[error("http request with middleware error: {0}")]
     HttpRequestWithMiddleware(#[from] reqwest_middleware::Error),
+    #[error("server error when fetching JWKS: status {status_code} - {response_text}")]
+    Jwks {
+        status_code: u16,
+        response_text: String,
+    },
+    #[error("invalid token: {0}")]
+    InvalidToken(String),
+    #[error("json web token error: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
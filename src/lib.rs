@@ -2,9 +2,14 @@ mod auth;
 mod client;
 mod error;
 mod service;
+mod store;
 mod sync;
 mod token;
+mod validation;
 
-pub use auth::KeycloakAuth;
+pub use auth::{KeycloakAuth, KeycloakAuthBuilder};
+pub use client::{Grant, OAuthError};
 pub use error::{Error, Result};
-pub use service::KeycloakService;
+pub use service::{KeycloakService, KeycloakValidationService};
+pub use store::{FileTokenStore, TokenStore};
+pub use validation::{Claims, KeycloakValidation};
@@ -1,10 +1,11 @@
-use futures_util::future::MapErr;
+use futures_util::future::{BoxFuture, MapErr};
 use futures_util::TryFutureExt;
+use hyper::{Request, Response, StatusCode};
 use std::task::{ready, Context, Poll};
 use tower_service::Service;
 use tracing::debug;
 
-use crate::{error, KeycloakAuth};
+use crate::{error, validation::Claims, KeycloakAuth, KeycloakValidation};
 
 #[derive(Clone)]
 pub struct KeycloakService<T> {
@@ -49,3 +50,97 @@ where
         self.inner.call(req).map_err(Error::Service)
     }
 }
+
+/// Inbound middleware that validates an incoming `Authorization: Bearer <jwt>`
+/// header against a realm's JWKS and rejects the request with a `401` on
+/// failure, instead of calling `inner`. On success, the decoded [`Claims`]
+/// are inserted into the request extensions.
+#[derive(Clone)]
+pub struct KeycloakValidationService<T> {
+    inner: T,
+    validation: KeycloakValidation,
+}
+
+impl<T> KeycloakValidationService<T> {
+    pub fn new(inner: T, validation: KeycloakValidation) -> Self {
+        Self { inner, validation }
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum ValidationError<E> {
+    #[error("inner service error: {0}")]
+    Service(E),
+    #[error("keycloak error: {0}")]
+    Keycloak(error::Error),
+}
+
+impl<T, ReqBody, ResBody> Service<Request<ReqBody>> for KeycloakValidationService<T>
+where
+    T: Service<Request<ReqBody>, Response = Response<ResBody>> + Clone + Send + 'static,
+    T::Future: Send,
+    T::Error: Send,
+    ReqBody: Send + 'static,
+    ResBody: Default,
+{
+    type Response = T::Response;
+    type Error = ValidationError<T::Error>;
+    type Future = BoxFuture<'static, Result<T::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        match ready!(self.validation.poll_ready(cx)) {
+            Ok(()) => self.inner.poll_ready(cx).map_err(ValidationError::Service),
+            Err(err) => {
+                debug!(?err);
+                Poll::Ready(Err(ValidationError::Keycloak(err)))
+            }
+        }
+    }
+
+    fn call(&mut self, mut req: Request<ReqBody>) -> Self::Future {
+        let token = req
+            .headers()
+            .get(hyper::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .map(|value| value.to_string());
+
+        let mut validation = self.validation.clone();
+
+        // Standard tower clone-and-swap: poll_ready already drove `self.inner`
+        // to readiness, so the not-yet-ready clone is left in its place and
+        // the ready original is moved into the returned future.
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        Box::pin(async move {
+            if let Some(token) = &token {
+                if !validation.has_key_for(token) {
+                    debug!("token references an unknown kid, refetching JWKS");
+                    if let Err(err) = validation.refetch().await {
+                        debug!(?err, "JWKS refetch failed");
+                    }
+                }
+            }
+
+            let claims = token
+                .as_deref()
+                .and_then(|token| validation.validate::<Claims>(token).ok());
+
+            match claims {
+                Some(claims) => {
+                    req.extensions_mut().insert(claims);
+                    inner.call(req).await.map_err(ValidationError::Service)
+                }
+                None => {
+                    debug!("rejecting request: missing or invalid bearer token");
+
+                    let mut response = Response::new(ResBody::default());
+                    *response.status_mut() = StatusCode::UNAUTHORIZED;
+
+                    Ok(response)
+                }
+            }
+        })
+    }
+}
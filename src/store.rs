@@ -0,0 +1,92 @@
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use crate::token::Token;
+
+/// Pluggable persistence for a fetched [`Token`](crate::token::Token), so
+/// that process restarts and multi-process deployments don't have to
+/// re-authenticate from scratch.
+pub trait TokenStore: Send + Sync {
+    fn load(&self) -> Option<Token>;
+    fn store(&self, token: &Token);
+}
+
+/// A [`TokenStore`] backed by a single file. Writes go to a sibling
+/// temporary path and are then renamed into place, so concurrent readers
+/// never observe a partially written token.
+pub struct FileTokenStore {
+    path: PathBuf,
+}
+
+impl FileTokenStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    fn tmp_path(&self) -> PathBuf {
+        let file_name = self
+            .path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("token");
+
+        self.path
+            .parent()
+            .unwrap_or_else(|| Path::new("."))
+            .join(format!(".{file_name}.tmp"))
+    }
+}
+
+impl TokenStore for FileTokenStore {
+    fn load(&self) -> Option<Token> {
+        let contents = std::fs::read(&self.path).ok()?;
+
+        match serde_json::from_slice(&contents) {
+            Ok(token) => Some(token),
+            Err(err) => {
+                warn!(?err, path = ?self.path, "failed to parse cached token, ignoring");
+                None
+            }
+        }
+    }
+
+    fn store(&self, token: &Token) {
+        let contents = match serde_json::to_vec(token) {
+            Ok(contents) => contents,
+            Err(err) => {
+                warn!(?err, "failed to serialize token for caching");
+                return;
+            }
+        };
+
+        let tmp_path = self.tmp_path();
+
+        if let Err(err) = std::fs::write(&tmp_path, contents) {
+            warn!(?err, path = ?tmp_path, "failed to write token cache file");
+            return;
+        }
+
+        if let Err(err) = restrict_to_owner(&tmp_path) {
+            warn!(?err, path = ?tmp_path, "failed to restrict token cache file permissions");
+            return;
+        }
+
+        if let Err(err) = std::fs::rename(&tmp_path, &self.path) {
+            warn!(?err, path = ?self.path, "failed to persist token cache file");
+        }
+    }
+}
+
+/// Restricts `path` to owner read/write, so a cached `access_token`/
+/// `refresh_token` isn't left group- or world-readable depending on the
+/// process umask. No-op on non-Unix targets.
+#[cfg(unix)]
+fn restrict_to_owner(path: &Path) -> std::io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+}
+
+#[cfg(not(unix))]
+fn restrict_to_owner(_path: &Path) -> std::io::Result<()> {
+    Ok(())
+}
@@ -1,30 +1,129 @@
 use hyper::http::HeaderValue;
-use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime};
 use tracing::instrument;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Token {
+    #[serde(with = "header_value")]
     pub header_value: HeaderValue,
-    pub expiration: Instant,
+    pub expiration: SystemTime,
+    pub refresh_token: Option<String>,
+    pub refresh_expiration: Option<SystemTime>,
+    pub expiry_delta: Duration,
 }
 
 impl Token {
-    const EXPIRY_DELTA: Duration = Duration::from_secs(10);
+    /// Default refresh margin when a caller doesn't configure one via
+    /// `KeycloakClientConfig`.
+    pub(crate) const EXPIRY_DELTA: Duration = Duration::from_secs(10);
 
     #[instrument(name = "new_token", level = "debug")]
-    pub fn new(token_type: &str, access_token: &str, expires_in: u64) -> Self {
+    pub fn new(
+        token_type: &str,
+        access_token: &str,
+        expires_in: u64,
+        refresh_token: Option<String>,
+        refresh_expires_in: Option<u64>,
+        expiry_delta: Duration,
+    ) -> Self {
         Self {
             header_value: HeaderValue::from_str(&format!("{token_type} {access_token}"))
                 .expect("Invalid access token"),
-            expiration: Instant::now() + Duration::from_secs(expires_in),
+            expiration: SystemTime::now() + Duration::from_secs(expires_in),
+            refresh_token,
+            refresh_expiration: refresh_expires_in.map(|refresh_expires_in| {
+                SystemTime::now() + Duration::from_secs(refresh_expires_in)
+            }),
+            expiry_delta,
         }
     }
 
     #[instrument(name = "token_is_expired", level = "debug", skip(self), ret)]
     pub fn is_expired(&self) -> bool {
         self.expiration
-            .checked_duration_since(Instant::now())
-            .map(|dur| dur < Self::EXPIRY_DELTA)
+            .duration_since(SystemTime::now())
+            .map(|dur| dur < self.expiry_delta)
             .unwrap_or(true)
     }
+
+    /// Whether this token carries a refresh token that can still be redeemed,
+    /// i.e. hasn't itself expired (when the server reports a lifetime for it).
+    #[instrument(name = "token_has_valid_refresh_token", level = "debug", skip(self), ret)]
+    pub fn has_valid_refresh_token(&self) -> bool {
+        if self.refresh_token.is_none() {
+            return false;
+        }
+
+        match self.refresh_expiration {
+            Some(refresh_expiration) => refresh_expiration
+                .duration_since(SystemTime::now())
+                .map(|dur| dur > self.expiry_delta)
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+/// (De)serializes a [`HeaderValue`] as a plain string, since it has no
+/// built-in `serde` support.
+mod header_value {
+    use hyper::http::HeaderValue;
+    use serde::{de::Error as _, ser::Error as _, Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(value: &HeaderValue, serializer: S) -> Result<S::Ok, S::Error> {
+        let value = value.to_str().map_err(S::Error::custom)?;
+        serializer.serialize_str(value)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<HeaderValue, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        HeaderValue::from_str(&value).map_err(D::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn token(expires_in: u64, refresh_token: Option<&str>, refresh_expires_in: Option<u64>) -> Token {
+        Token::new(
+            "Bearer",
+            "access-token",
+            expires_in,
+            refresh_token.map(String::from),
+            refresh_expires_in,
+            Token::EXPIRY_DELTA,
+        )
+    }
+
+    #[test]
+    fn is_expired_true_within_expiry_delta() {
+        assert!(token(5, None, None).is_expired());
+    }
+
+    #[test]
+    fn is_expired_false_well_before_expiry() {
+        assert!(!token(3600, None, None).is_expired());
+    }
+
+    #[test]
+    fn has_valid_refresh_token_false_without_one() {
+        assert!(!token(3600, None, None).has_valid_refresh_token());
+    }
+
+    #[test]
+    fn has_valid_refresh_token_true_without_expiry() {
+        assert!(token(3600, Some("refresh"), None).has_valid_refresh_token());
+    }
+
+    #[test]
+    fn has_valid_refresh_token_false_once_within_expiry_delta() {
+        assert!(!token(3600, Some("refresh"), Some(5)).has_valid_refresh_token());
+    }
+
+    #[test]
+    fn has_valid_refresh_token_true_well_before_expiry() {
+        assert!(token(3600, Some("refresh"), Some(3600)).has_valid_refresh_token());
+    }
 }
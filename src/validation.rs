@@ -0,0 +1,209 @@
+use futures_util::future::{poll_fn, BoxFuture};
+use jsonwebtoken::{decode, decode_header, DecodingKey, Validation};
+use parking_lot::RwLock;
+use serde::{de::DeserializeOwned, Deserialize};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+use tracing::trace;
+
+use crate::{
+    client::{Jwk, Jwks, KeycloakJwksClient},
+    error::{Error, Result},
+    sync::RefGuard,
+};
+
+pub type JwksResponseFuture = BoxFuture<'static, Result<Jwks>>;
+
+/// Standard claims extracted from a validated access token and inserted into
+/// request extensions by [`KeycloakValidationService`](crate::service::KeycloakValidationService).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Claims {
+    pub sub: String,
+    pub iss: String,
+    pub exp: u64,
+    #[serde(default)]
+    pub nbf: Option<u64>,
+    #[serde(default)]
+    pub aud: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Clone)]
+pub struct KeycloakValidation {
+    inner: Arc<RwLock<KeycloakValidationInner>>,
+}
+
+impl KeycloakValidation {
+    pub fn new(server_url: String, realm: String) -> Result<Self> {
+        Ok(Self {
+            inner: Arc::new(RwLock::new(KeycloakValidationInner::new(
+                KeycloakJwksClient::new(format!(
+                    "{server_url}/realms/{realm}/protocol/openid-connect/certs"
+                ))?,
+                format!("{server_url}/realms/{realm}"),
+            ))),
+        })
+    }
+
+    pub fn with_audience(self, audience: String) -> Self {
+        self.inner.write().expected_audience = Some(audience);
+        self
+    }
+
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        if self.inner.read().can_skip_poll_ready() {
+            return Poll::Ready(Ok(()));
+        }
+
+        self.inner.write().poll_ready(cx)
+    }
+
+    /// Validates a raw `Bearer` token against the cached realm JWKS and
+    /// returns its claims. A token whose `kid` is not cached fails with
+    /// [`Error::InvalidToken`]; this method does not refetch the JWKS (see
+    /// [`has_key_for`](Self::has_key_for) and [`refetch`](Self::refetch)).
+    pub fn validate<T: DeserializeOwned>(&self, token: &str) -> Result<T> {
+        let header = decode_header(token).map_err(|err| Error::InvalidToken(err.to_string()))?;
+
+        let kid = header
+            .kid
+            .ok_or_else(|| Error::InvalidToken("missing kid in token header".into()))?;
+
+        let inner = self.inner.read();
+
+        let jwk = inner
+            .keys
+            .get(&kid)
+            .ok_or_else(|| Error::InvalidToken(format!("unknown kid: {kid}")))?;
+
+        let decoding_key = decoding_key(jwk)?;
+
+        let mut validation = Validation::new(header.alg);
+        validation.set_issuer(&[&inner.issuer]);
+        validation.validate_nbf = true;
+
+        if let Some(audience) = &inner.expected_audience {
+            validation.set_audience(&[audience]);
+        } else {
+            validation.validate_aud = false;
+        }
+
+        let token_data = decode::<T>(token, &decoding_key, &validation)
+            .map_err(|err| Error::InvalidToken(err.to_string()))?;
+
+        Ok(token_data.claims)
+    }
+
+    /// Returns `true` if the cached JWKS holds a key for `token`'s `kid`, and
+    /// `false` if that `kid` is unknown or the token header can't be decoded
+    /// or carries no `kid`, so callers can trigger a refetch.
+    pub fn has_key_for(&self, token: &str) -> bool {
+        let Ok(header) = decode_header(token) else {
+            return false;
+        };
+
+        let Some(kid) = header.kid else {
+            return false;
+        };
+
+        self.inner.read().keys.contains_key(&kid)
+    }
+
+    pub fn force_refetch(&mut self) {
+        self.inner.write().state = State::NotFetched;
+    }
+
+    /// Forces a JWKS refetch and waits for it to complete, e.g. after a
+    /// token referenced a `kid` not present in the cache.
+    pub async fn refetch(&mut self) -> Result<()> {
+        self.force_refetch();
+        poll_fn(|cx| self.poll_ready(cx)).await
+    }
+}
+
+fn decoding_key(jwk: &Jwk) -> Result<DecodingKey> {
+    match jwk.kty.as_str() {
+        "RSA" => match (jwk.n.as_deref(), jwk.e.as_deref()) {
+            (Some(n), Some(e)) => DecodingKey::from_rsa_components(n, e)
+                .map_err(|err| Error::InvalidToken(err.to_string())),
+            _ => Err(Error::InvalidToken(format!(
+                "incomplete RSA jwk: {}",
+                jwk.kid
+            ))),
+        },
+        "EC" => match (jwk.x.as_deref(), jwk.y.as_deref()) {
+            (Some(x), Some(y)) => DecodingKey::from_ec_components(x, y)
+                .map_err(|err| Error::InvalidToken(err.to_string())),
+            _ => Err(Error::InvalidToken(format!("incomplete EC jwk: {}", jwk.kid))),
+        },
+        kty => Err(Error::InvalidToken(format!(
+            "unsupported jwk kty {kty}: {}",
+            jwk.kid
+        ))),
+    }
+}
+
+struct KeycloakValidationInner {
+    state: State,
+    client: KeycloakJwksClient,
+    issuer: String,
+    expected_audience: Option<String>,
+    keys: HashMap<String, Jwk>,
+}
+
+impl KeycloakValidationInner {
+    pub fn new(client: KeycloakJwksClient, issuer: String) -> Self {
+        Self {
+            state: State::NotFetched,
+            client,
+            issuer,
+            expected_audience: None,
+            keys: HashMap::new(),
+        }
+    }
+
+    #[inline]
+    pub fn can_skip_poll_ready(&self) -> bool {
+        matches!(self.state, State::Fetched) && !self.keys.is_empty()
+    }
+
+    #[inline]
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        loop {
+            match self.state {
+                State::NotFetched => {
+                    trace!("State::NotFetched");
+
+                    self.state = State::Fetching {
+                        fut: RefGuard::new(self.client.fetch_jwks_boxed()),
+                    };
+                }
+                State::Fetching { ref mut fut } => match ready!(fut.get_mut().as_mut().poll(cx)) {
+                    Ok(jwks) => {
+                        trace!(count = jwks.keys.len(), "State::Fetching");
+
+                        self.keys = jwks.keys.into_iter().map(|key| (key.kid.clone(), key)).collect();
+                        self.state = State::Fetched;
+
+                        return Poll::Ready(Ok(()));
+                    }
+                    Err(err) => {
+                        self.state = State::NotFetched;
+                        return Poll::Ready(Err(err));
+                    }
+                },
+                State::Fetched => return Poll::Ready(Ok(())),
+            }
+        }
+    }
+}
+
+pub(crate) enum State {
+    NotFetched,
+    Fetching { fut: RefGuard<JwksResponseFuture> },
+    Fetched,
+}